@@ -19,6 +19,7 @@ use crate::{
 	Error,
 	wasm::{Runtime, RuntimeToken},
 };
+use codec::{Decode, Encode};
 use frame_support::weights::Weight;
 use sp_runtime::DispatchError;
 use sp_core::crypto::UncheckedFrom;
@@ -38,6 +39,13 @@ pub trait ChainExtension {
 	fn enabled() -> bool {
 		true
 	}
+
+	/// Describes the function IDs this extension answers to, so off-chain tooling can
+	/// generate typed client bindings without hand-written glue. Empty by default so
+	/// existing extensions don't have to opt in.
+	fn metadata() -> Vec<ExtensionFnMeta> {
+		Vec::new()
+	}
 }
 
 impl ChainExtension for () {
@@ -54,6 +62,104 @@ impl ChainExtension for () {
 	}
 }
 
+/// The calling convention a chain-extension function uses, mirroring the `state` typestates
+/// that gate which of `val0`/`val1`/`read`/`write` an `Environment` exposes.
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+pub enum StateKind {
+	OnlyIn,
+	PrimInBufOut,
+	BufInBufOut,
+}
+
+/// A self-description of a single chain-extension function, for off-chain tooling that wants
+/// to generate typed client bindings the way it already does from runtime metadata.
+///
+/// The name/type fields own their bytes (rather than borrowing `&'static str`) so the whole
+/// struct can `Decode`: it is the return type of `ContractsApi::chain_extension_metadata`, and
+/// the client side of that runtime API decodes it back out of SCALE-encoded bytes, which is
+/// impossible for a `'static` borrow.
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+pub struct ExtensionFnMeta {
+	pub id: u32,
+	pub name: Vec<u8>,
+	pub input_ty: Vec<u8>,
+	pub output_ty: Vec<u8>,
+	pub state: StateKind,
+}
+
+impl ExtensionFnMeta {
+	pub fn new(
+		id: u32,
+		name: &'static str,
+		input_ty: &'static str,
+		output_ty: &'static str,
+		state: StateKind,
+	) -> Self {
+		Self {
+			id,
+			name: name.as_bytes().to_vec(),
+			input_ty: input_ty.as_bytes().to_vec(),
+			output_ty: output_ty.as_bytes().to_vec(),
+			state,
+		}
+	}
+}
+
+/// Implements `ChainExtension` for tuples of `ChainExtension`s, dispatching by the high 16
+/// bits of `func_id`. Tuple position `0` claims `ext_id` `0`, position `1` claims `ext_id`
+/// `1`, and so on, so several independently authored extensions can be combined in a
+/// runtime's `type ChainExtension = (Foo, Bar, Baz);` without any of them knowing about the
+/// others.
+macro_rules! impl_chain_extension_tuple {
+	($($ext:ident),+) => {
+		impl<$($ext: ChainExtension),+> ChainExtension for ($($ext,)+) {
+			fn call<E: Ext>(func_id: u32, env: Environment<E, state::Init>) -> Result<RetVal>
+			where
+				<E::T as SysTrait>::AccountId: UncheckedFrom<<E::T as SysTrait>::Hash> + AsRef<[u8]>,
+			{
+				let ext_id = env.ext_id();
+				let mut pos = 0u32;
+				$(
+					if pos == ext_id {
+						return if $ext::enabled() {
+							$ext::call(func_id, env)
+						} else {
+							Err(Error::<E::T>::NoChainExtension.into())
+						};
+					}
+					pos += 1;
+				)+
+				let _ = pos;
+				Err(Error::<E::T>::NoChainExtension.into())
+			}
+
+			fn metadata() -> Vec<ExtensionFnMeta> {
+				let mut meta = Vec::new();
+				let mut pos = 0u32;
+				$(
+					let ext_id = pos << 16;
+					meta.extend($ext::metadata().into_iter().map(|mut fn_meta| {
+						fn_meta.id |= ext_id;
+						fn_meta
+					}));
+					pos += 1;
+				)+
+				let _ = pos;
+				meta
+			}
+		}
+	};
+}
+
+impl_chain_extension_tuple!(X0);
+impl_chain_extension_tuple!(X0, X1);
+impl_chain_extension_tuple!(X0, X1, X2);
+impl_chain_extension_tuple!(X0, X1, X2, X3);
+impl_chain_extension_tuple!(X0, X1, X2, X3, X4);
+impl_chain_extension_tuple!(X0, X1, X2, X3, X4, X5);
+impl_chain_extension_tuple!(X0, X1, X2, X3, X4, X5, X6);
+impl_chain_extension_tuple!(X0, X1, X2, X3, X4, X5, X6, X7);
+
 pub enum RetVal {
 	Converging(u32),
 	Diverging{flags: ReturnFlags, data: Vec<u8>},
@@ -61,6 +167,7 @@ pub enum RetVal {
 
 struct Inner<'a, 'b, E: Ext> {
 	runtime: &'a mut Runtime::<'b, E>,
+	func_id: u32,
 	input_ptr: u32,
 	input_len: u32,
 	output_ptr: u32,
@@ -74,6 +181,7 @@ pub struct Environment<'a, 'b, E: Ext, S: state::State> {
 
 pub(crate) fn environment<'a, 'b, E: Ext>(
 	runtime: &'a mut Runtime::<'b, E>,
+	func_id: u32,
 	input_ptr: u32,
 	input_len: u32,
 	output_ptr: u32,
@@ -83,6 +191,7 @@ pub(crate) fn environment<'a, 'b, E: Ext>(
 	Environment {
 		inner: Inner {
 			runtime,
+			func_id,
 			input_ptr,
 			input_len,
 			output_ptr,
@@ -100,9 +209,27 @@ where
 		self.inner.runtime.charge_gas(RuntimeToken::ChainExtension(amount))
 	}
 
+	/// Credits back weight that was charged via `charge_weight` but turned out not to be
+	/// consumed, e.g. the unused portion of a `weight_limit` passed to a foreign call.
+	pub fn refund_weight(&mut self, amount: Weight) {
+		self.inner.runtime.refund_gas(RuntimeToken::ChainExtension(amount))
+	}
+
 	pub fn ext(&mut self) -> &mut E {
 		self.inner.runtime.ext()
 	}
+
+	/// The function selector within the extension that `ext_id()` routed to, i.e. the
+	/// low 16 bits of the raw `func_id` passed to `ChainExtension::call`.
+	pub fn func_id(&self) -> u32 {
+		self.inner.func_id & 0x0000FFFF
+	}
+
+	/// Which registered extension this call is addressed to, i.e. the high 16 bits of
+	/// the raw `func_id` passed to `ChainExtension::call`.
+	pub fn ext_id(&self) -> u32 {
+		self.inner.func_id >> 16
+	}
 }
 
 impl<'a, 'b, E: Ext> Environment<'a, 'b, E, state::Init> {
@@ -155,6 +282,48 @@ where
 	pub fn read(&self) -> Result<Vec<u8>> {
 		self.inner.runtime.read_sandbox_memory(self.inner.input_ptr, self.inner.input_len)
 	}
+
+	/// Decodes the input as a `T` whose encoded length is bounded independently of the
+	/// contract-supplied `input_len` (e.g. a fixed-size struct or enum). Dynamically sized or
+	/// otherwise attacker-influenced types must go through [`read_as_unbounded`] instead, so
+	/// the allocation size is capped before any bytes are read.
+	///
+	/// [`read_as_unbounded`]: Self::read_as_unbounded
+	pub fn read_as<T: Decode>(&self) -> Result<T> {
+		let bytes = self.inner.runtime.read_sandbox_memory(self.inner.input_ptr, self.inner.input_len)?;
+		T::decode(&mut &bytes[..]).map_err(|_| Error::<E::T>::DecodingFailed.into())
+	}
+
+	/// Like [`read_as`](Self::read_as), but enforces `len_limit` against the contract-supplied
+	/// `input_len` before allocating, so a malicious contract cannot force a huge host
+	/// allocation by decoding an unbounded or attacker-sized type.
+	pub fn read_as_unbounded<T: Decode>(&self, len_limit: u32) -> Result<T> {
+		if self.inner.input_len > len_limit {
+			return Err(Error::<E::T>::DecodingFailed.into());
+		}
+		let bytes = self.inner.runtime.read_sandbox_memory(self.inner.input_ptr, self.inner.input_len)?;
+		T::decode(&mut &bytes[..]).map_err(|_| Error::<E::T>::DecodingFailed.into())
+	}
+
+	/// The length of the contract-supplied input, in bytes.
+	pub fn in_len(&self) -> u32 {
+		self.inner.input_len
+	}
+
+	/// Copies at most `buf.len()` bytes of the contract-supplied input into `buf`, without
+	/// allocating, and returns the initialized prefix.
+	///
+	/// Errors if the input is longer than `buf` unless `truncate` is set, in which case the
+	/// input is silently cut off at `buf.len()`.
+	pub fn read_into<'c>(&self, buf: &'c mut [u8], truncate: bool) -> Result<&'c mut [u8]> {
+		if self.inner.input_len as usize > buf.len() && !truncate {
+			return Err(Error::<E::T>::BufferTooSmall.into());
+		}
+		let len = buf.len().min(self.inner.input_len as usize) as u32;
+		let out = &mut buf[..len as usize];
+		self.inner.runtime.read_sandbox_memory_into(self.inner.input_ptr, out)?;
+		Ok(out)
+	}
 }
 
 impl<'a, 'b, E: Ext, S: state::BufOut> Environment<'a, 'b, E, S>
@@ -177,6 +346,15 @@ where
 			},
 		)
 	}
+
+	pub fn write_as<T: Encode>(
+		&mut self,
+		value: &T,
+		allow_skip: bool,
+		weight_per_byte: Option<Weight>,
+	) -> Result<()> {
+		self.write(&value.encode(), allow_skip, weight_per_byte)
+	}
 }
 
 mod state {
@@ -204,3 +382,92 @@ mod state {
 	impl BufIn for BufInBufOut {}
 	impl BufOut for BufInBufOut {}
 }
+
+/// A provided `ChainExtension` that lets a Wasm contract invoke a contract hosted on another
+/// registered VM (e.g. an EVM pallet) and get its return data back, without the runtime having
+/// to hand-roll its own cross-VM plumbing on top of `Ext`.
+pub mod xvm {
+	use super::*;
+
+	/// Identifies a registered virtual machine that a cross-VM call can target.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+	pub enum VmId {
+		Evm,
+		Wasm,
+	}
+
+	/// The `BufInBufOut` payload accepted by [`XvmExtension`].
+	#[derive(Encode, Decode)]
+	pub struct XvmCall<Balance> {
+		pub target_vm: VmId,
+		pub callee: Vec<u8>,
+		pub input: Vec<u8>,
+		pub value: Balance,
+		pub weight_limit: Weight,
+	}
+
+	/// The only function this extension answers to: a cross-VM call.
+	const FUNC_ID_CALL: u32 = 0;
+
+	/// Upper bound on the encoded size of an [`XvmCall`], so decoding one cannot force an
+	/// unbounded host allocation from the attacker-controlled `callee`/`input` byte vectors.
+	const MAX_CALL_LEN: u32 = 64 * 1024;
+
+	/// Dispatches a Wasm contract's call into another VM via `Ext::call_foreign_vm`.
+	///
+	/// On success the callee's output is written back to the contract and `Converging(0)` is
+	/// returned. On revert the callee's output is surfaced as `Diverging` data so the calling
+	/// contract can observe the foreign VM's revert reason instead of the call merely failing.
+	pub struct XvmExtension;
+
+	impl ChainExtension for XvmExtension {
+		fn call<E: Ext>(_func_id: u32, env: Environment<E, state::Init>) -> Result<RetVal>
+		where
+			<E::T as SysTrait>::AccountId: UncheckedFrom<<E::T as SysTrait>::Hash> + AsRef<[u8]>,
+		{
+			let mut env = env.buf_in_buf_out();
+
+			if env.func_id() != FUNC_ID_CALL {
+				return Err(Error::<E::T>::NoChainExtension.into());
+			}
+
+			// `callee`/`input` are attacker-controlled and unbounded in size, so this must go
+			// through the bounded decode path rather than `read_as`.
+			let call: XvmCall<crate::BalanceOf<E::T>> = env.read_as_unbounded(MAX_CALL_LEN)?;
+			let weight_limit = call.weight_limit;
+
+			env.charge_weight(weight_limit)?;
+			let result = env.ext().call_foreign_vm(
+				call.target_vm,
+				call.callee,
+				call.input,
+				call.value,
+				weight_limit,
+			);
+
+			let (consumed, outcome) = match result {
+				Ok((output, consumed)) => (consumed, Ok(output)),
+				Err((output, consumed)) => (consumed, Err(output)),
+			};
+			env.refund_weight(weight_limit.saturating_sub(consumed));
+
+			match outcome {
+				Ok(output) => {
+					env.write(&output, false, None)?;
+					Ok(RetVal::Converging(0))
+				},
+				Err(output) => Ok(RetVal::Diverging { flags: ReturnFlags::REVERT, data: output }),
+			}
+		}
+
+		fn metadata() -> Vec<ExtensionFnMeta> {
+			vec![ExtensionFnMeta::new(
+				FUNC_ID_CALL,
+				"call",
+				"XvmCall<BalanceOf<T>>",
+				"Vec<u8>",
+				StateKind::BufInBufOut,
+			)]
+		}
+	}
+}
@@ -0,0 +1,45 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{chain_extension::xvm::VmId, BalanceOf};
+use frame_support::weights::Weight;
+
+/// The interface a contract's execution frame exposes to the chain extension it calls into.
+pub trait Ext {
+	type T: crate::Trait;
+
+	/// The account that originated the contract call currently executing.
+	fn caller(&mut self) -> &<Self::T as frame_system::Trait>::AccountId;
+
+	/// Dispatches a call into another registered VM (e.g. an EVM pallet) on behalf of the
+	/// currently executing contract, metering the callee's execution against `weight_limit`.
+	///
+	/// Returns the callee's raw output together with the weight actually consumed, so the
+	/// caller can refund the unused portion of `weight_limit` rather than charging the full
+	/// limit regardless of what the foreign VM spent. On revert, the callee's output is still
+	/// returned (as `Err`, alongside the weight consumed up to the revert point), so the
+	/// calling contract can surface *why* the foreign call failed rather than just seeing a
+	/// bare dispatch error.
+	fn call_foreign_vm(
+		&mut self,
+		target_vm: VmId,
+		callee: Vec<u8>,
+		input: Vec<u8>,
+		value: BalanceOf<Self::T>,
+		weight_limit: Weight,
+	) -> Result<(Vec<u8>, Weight), (Vec<u8>, Weight)>;
+}
@@ -0,0 +1,72 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod chain_extension;
+mod exec;
+mod wasm;
+
+use frame_support::{decl_error, decl_module, traits::Currency};
+
+pub trait Trait: frame_system::Trait {
+	/// The currency used to denominate contract balances, e.g. the `value` transferred in a
+	/// cross-VM call.
+	type Currency: Currency<Self::AccountId>;
+
+	/// The chain extension(s) available to contracts running under this runtime. Use a tuple
+	/// to combine several independently authored extensions.
+	type ChainExtension: chain_extension::ChainExtension;
+}
+
+pub type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// No chain extension claimed the function id passed to `seal_call_chain_extension`.
+		NoChainExtension,
+		/// Input passed to a chain extension function failed to decode as the expected type.
+		DecodingFailed,
+		/// The destination buffer passed to `read_into` is smaller than the contract-supplied
+		/// input and `truncate` was not set.
+		BufferTooSmall,
+		/// A host function tried to read or write sandbox memory outside of its bounds.
+		OutOfBounds,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The aggregated metadata of every chain-extension function `T::ChainExtension` answers
+	/// to, for `ContractsApi::chain_extension_metadata` to surface to off-chain tooling.
+	pub fn chain_extension_metadata() -> sp_std::vec::Vec<chain_extension::ExtensionFnMeta> {
+		<T::ChainExtension as chain_extension::ChainExtension>::metadata()
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime API consumed by off-chain tooling (the way `subxt` consumes runtime metadata)
+	/// to discover which chain-extension functions a runtime exposes.
+	pub trait ContractsApi {
+		/// See [`Module::chain_extension_metadata`].
+		fn chain_extension_metadata() -> sp_std::vec::Vec<chain_extension::ExtensionFnMeta>;
+	}
+}
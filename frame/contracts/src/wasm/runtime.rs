@@ -0,0 +1,124 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	chain_extension::{self, ChainExtension, RetVal},
+	exec::Ext,
+	Error, Trait,
+};
+use frame_support::weights::Weight;
+use sp_core::crypto::UncheckedFrom;
+
+/// Gas tokens charged by chain-extension host functions through `Environment::charge_weight`
+/// (and credited back through `Environment::refund_weight`).
+pub enum RuntimeToken {
+	ChainExtension(Weight),
+}
+
+/// The host-function execution context threaded through a contract's Wasm sandbox call.
+pub struct Runtime<'a, E: Ext> {
+	ext: &'a mut E,
+	memory: Vec<u8>,
+	gas_left: Weight,
+}
+
+impl<'a, E: Ext> Runtime<'a, E> {
+	pub fn ext(&mut self) -> &mut E {
+		self.ext
+	}
+
+	pub fn charge_gas(&mut self, token: RuntimeToken) -> chain_extension::Result<()> {
+		let RuntimeToken::ChainExtension(amount) = token;
+		self.gas_left = self.gas_left.saturating_sub(amount);
+		Ok(())
+	}
+
+	/// Credits back gas that was charged up front but not actually consumed, e.g. the unused
+	/// portion of an `XvmExtension` call's `weight_limit`.
+	pub fn refund_gas(&mut self, token: RuntimeToken) {
+		let RuntimeToken::ChainExtension(amount) = token;
+		self.gas_left = self.gas_left.saturating_add(amount);
+	}
+
+	pub fn read_sandbox_memory(&self, ptr: u32, len: u32) -> chain_extension::Result<Vec<u8>> {
+		let (ptr, len) = (ptr as usize, len as usize);
+		self.memory.get(ptr..ptr + len)
+			.map(|bytes| bytes.to_vec())
+			.ok_or_else(|| Error::<E::T>::OutOfBounds.into())
+	}
+
+	/// Copies sandbox memory straight into `buf` without an intermediate allocation, for
+	/// callers (like `Environment::read_into`) that already own a scratch buffer.
+	pub fn read_sandbox_memory_into(&self, ptr: u32, buf: &mut [u8]) -> chain_extension::Result<()> {
+		let ptr = ptr as usize;
+		let src = self.memory.get(ptr..ptr + buf.len())
+			.ok_or_else(|| Error::<E::T>::OutOfBounds)?;
+		buf.copy_from_slice(src);
+		Ok(())
+	}
+
+	pub fn write_sandbox_output(
+		&mut self,
+		out_ptr: u32,
+		out_len_ptr: u32,
+		buf: &[u8],
+		allow_skip: bool,
+		weight_per_byte: impl FnOnce(u32) -> Option<RuntimeToken>,
+	) -> chain_extension::Result<()> {
+		if allow_skip {
+			return Ok(());
+		}
+		if let Some(token) = weight_per_byte(buf.len() as u32) {
+			self.charge_gas(token)?;
+		}
+		let out_ptr = out_ptr as usize;
+		self.memory.get_mut(out_ptr..out_ptr + buf.len())
+			.ok_or_else(|| Error::<E::T>::OutOfBounds)?
+			.copy_from_slice(buf);
+		let out_len_ptr = out_len_ptr as usize;
+		self.memory.get_mut(out_len_ptr..out_len_ptr + 4)
+			.ok_or_else(|| Error::<E::T>::OutOfBounds)?
+			.copy_from_slice(&(buf.len() as u32).to_le_bytes());
+		Ok(())
+	}
+
+	/// The `seal_call_chain_extension` host function: hands `T::ChainExtension` the raw
+	/// `func_id` the contract passed in, so extensions (and the dispatch-by-prefix tuple impl)
+	/// can see which function was requested instead of only seeing a pre-split id.
+	pub fn call_chain_extension(
+		&mut self,
+		func_id: u32,
+		input_ptr: u32,
+		input_len: u32,
+		output_ptr: u32,
+		output_len_ptr: u32,
+	) -> chain_extension::Result<RetVal>
+	where
+		<E::T as frame_system::Trait>::AccountId:
+			UncheckedFrom<<E::T as frame_system::Trait>::Hash> + AsRef<[u8]>,
+	{
+		let env = chain_extension::environment(
+			self,
+			func_id,
+			input_ptr,
+			input_len,
+			output_ptr,
+			output_len_ptr,
+		);
+		<E::T as Trait>::ChainExtension::call(func_id, env)
+	}
+}